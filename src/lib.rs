@@ -19,66 +19,268 @@
 // 
 
 // Dependencies
-use failure::{Fail};
+use thiserror::Error;
 use serde::*;
 use juniper::*;
 use std::convert::From;
-use cubeos_error::Error;
+use cubeos_error::Error as CubeOSError;
 
 mod example;
 
 // Make everything in example.rs public
 pub use crate::example::*;
 
+/// I2C bus errors.
+#[derive(Debug, Error)]
+pub enum I2cError {
+    /// I2C Error
+    #[error("I2C error: {0:?}")]
+    I2CError(#[from] std::io::Error),
+    /// I2C Set Error
+    #[error("I2C Set Error")]
+    I2CSet,
+}
+
+/// `std::io::Error` implements neither `Clone` nor `PartialEq`, so they
+/// can't be derived here; hand-roll them on its `kind()` and message so
+/// the full error (not just its kind) is still what gets stored and
+/// displayed.
+impl Clone for I2cError {
+    fn clone(&self) -> I2cError {
+        match self {
+            I2cError::I2CError(e) => I2cError::I2CError(std::io::Error::new(e.kind(), e.to_string())),
+            I2cError::I2CSet => I2cError::I2CSet,
+        }
+    }
+}
+
+impl PartialEq for I2cError {
+    fn eq(&self, other: &I2cError) -> bool {
+        match (self, other) {
+            (I2cError::I2CError(a), I2cError::I2CError(b)) => {
+                a.kind() == b.kind() && a.to_string() == b.to_string()
+            }
+            (I2cError::I2CSet, I2cError::I2CSet) => true,
+            _ => false,
+        }
+    }
+}
+
+/// UART bus errors.
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum UartError {
+    /// UART Error
+    #[error("UART error: {0}")]
+    UARTError(#[from] rust_uart::UartError),
+}
+
 // Example Error type
 // covers all Errors possible within your API, Service and Payload
-#[derive(Debug, Fail, Clone, PartialEq)]
+#[derive(Debug, Error, Clone, PartialEq)]
 pub enum ExampleError {
     /// None
-    #[fail(display = "None")]
+    #[error("None")]
     None,
     /// Example error
-    #[fail(display = "Example error")]
+    #[error("Example error")]
     Err,
     /// Set Error
-    #[fail(display = "Set error, only accepts ZERO or ONE")]
+    #[error("Set error, only accepts ZERO or ONE")]
     SetErr,
-    /// I2C Error
-    #[fail(display = "I2C Error")]
-    I2CError(std::io::ErrorKind),
-    /// I2C Set Error
-    #[fail(display = "I2C Set Error")]
-    I2CSet,
-    /// UART Error
-    #[fail(display = "UART Error")]
-    UARTError(rust_uart::UartError),
+    /// I2C bus error
+    #[error(transparent)]
+    I2c(#[from] I2cError),
+    /// UART bus error
+    #[error(transparent)]
+    Uart(#[from] UartError),
+}
+
+/// Stable wire codes for the `ExampleError` variants that are reported to
+/// GND as a `cubeos_error::Error::ServiceError(u8)`. This table is the single
+/// source of truth for the encoding: `From<ExampleError>` writes it,
+/// `TryFrom<cubeos_error::Error>` reads it back, so a received
+/// `ServiceError(n)` can be decoded into the original variant instead of
+/// staying an opaque integer on the ground station.
+///
+/// Each bus sub-error owns a fixed, non-overlapping range of the `u8`
+/// space (`I2C_CODE_BASE..UART_CODE_BASE`, `UART_CODE_BASE..`) so payload
+/// authors can add new buses, and new variants to existing buses, without
+/// renumbering or colliding with codes already in use.
+///
+/// Confirmed scope: decoding is exact only for variants that carry no
+/// payload (`None`, `Err`, `SetErr`, `I2CSet`). `I2cError::I2CError` and
+/// `UartError::UARTError` wrap a `std::io::Error`/`rust_uart::UartError`
+/// whose message can't fit in a `u8`, so decoding their `ServiceError` code
+/// returns `Err(ServiceError(code))` (not `ExampleError::Err`) rather than a
+/// fabricated, incomplete reconstruction — see the
+/// `does_not_round_trip_*` tests below for that contract.
+const NONE_CODE: u8 = 0;
+const ERR_CODE: u8 = 1;
+const SET_ERR_CODE: u8 = 2;
+const I2C_CODE_BASE: u8 = 10;
+const UART_CODE_BASE: u8 = 20;
+
+impl I2cError {
+    /// Wire code of this variant: `I2C_CODE_BASE` plus its offset in that range.
+    fn code(&self) -> u8 {
+        I2C_CODE_BASE
+            + match self {
+                I2cError::I2CError(_) => 0,
+                I2cError::I2CSet => 1,
+            }
+    }
+
+    /// Inverse of `code()`: decodes a `ServiceError` code back into the
+    /// variant it identifies, for variants that carry no payload (so there
+    /// is nothing the code alone fails to reconstruct). Returns `None` for
+    /// codes outside this bus's range, and for payload-carrying variants.
+    fn decode(code: u8) -> Option<I2cError> {
+        match code.checked_sub(I2C_CODE_BASE)? {
+            1 => Some(I2cError::I2CSet),
+            _ => None,
+        }
+    }
+}
+
+impl UartError {
+    /// Wire code of this variant: `UART_CODE_BASE` plus its offset in that range.
+    fn code(&self) -> u8 {
+        UART_CODE_BASE
+            + match self {
+                UartError::UARTError(_) => 0,
+            }
+    }
+
+    /// Inverse of `code()`. UART has no payload-free variant yet, so every
+    /// code in its range currently decodes to `None`; this still keeps the
+    /// range check in one place alongside `code()` instead of inlined at
+    /// each call site.
+    fn decode(code: u8) -> Option<UartError> {
+        let _offset = code.checked_sub(UART_CODE_BASE)?;
+        None
+    }
 }
-/// Implementation of Conversion of Example Error type 
+
+/// Implementation of Conversion of Example Error type
 /// to cubeos_error::Error (Error type that gets returned to GND)
-/// 
+///
 /// cubeos-error::Error implements conversion for the following standard errors:
 /// failure::Error -> cubeos_error::Error::Failure(String)
 /// std::io::Error -> cubeos_error::Error::Io(u8)
 /// Infallible -> cubeos_error::Error::Infallible
 /// bincode::Error -> cubeos_error::Error::Bincode(u8)
 /// PoisonError<MutexGuard<'a,T>> -> cubeos_error::Error::PoisonError
-/// 
-/// Any Errors in ExampleError must be converted to cubeos_error::Error::ServiceError(u8)
-impl From<ExampleError> for Error {
-    fn from(e: ExampleError) -> cubeos_error::Error {
+///
+/// `ExampleError` itself does not take the `std::io::Error -> Error::Io(u8)`
+/// path above: its I2C/UART variants are bus sub-errors (`I2cError`,
+/// `UartError`), and every `ExampleError` variant is reported as
+/// `cubeos_error::Error::ServiceError(u8)` using the per-bus code ranges
+/// below, not `Error::Io`. That trades the richer detail `Error::Io` would
+/// have carried for a single fixed code per payload-carrying variant
+/// (10 for `I2CError`, 20 for `UARTError`).
+impl From<ExampleError> for CubeOSError {
+    fn from(e: ExampleError) -> CubeOSError {
+        match e {
+            ExampleError::None => CubeOSError::ServiceError(NONE_CODE),
+            ExampleError::Err => CubeOSError::ServiceError(ERR_CODE),
+            ExampleError::SetErr => CubeOSError::ServiceError(SET_ERR_CODE),
+            ExampleError::I2c(sub) => CubeOSError::ServiceError(sub.code()),
+            ExampleError::Uart(sub) => CubeOSError::ServiceError(sub.code()),
+        }
+    }
+}
+
+/// Inverse of `From<ExampleError> for CubeOSError`: decodes a
+/// `ServiceError(u8)` received from the service back into the typed
+/// `ExampleError` it was raised from, so GND can match on the variant
+/// (and print its `Display` message, e.g. "Set error, only accepts ZERO
+/// or ONE") instead of a bare code.
+///
+/// Variants that wrap a payload (`I2cError::I2CError`'s `std::io::Error`,
+/// `UartError::UARTError`'s `rust_uart::UartError`) cannot be rebuilt from a
+/// bare code alone, since that detail was never put on the wire in the
+/// first place; those codes are returned unchanged as `Err` so no
+/// information is silently fabricated.
+impl std::convert::TryFrom<CubeOSError> for ExampleError {
+    type Error = CubeOSError;
+
+    fn try_from(e: CubeOSError) -> Result<Self, Self::Error> {
         match e {
-            ExampleError::None => cubeos_error::Error::ServiceError(0),
-            ExampleError::Err => cubeos_error::Error::ServiceError(1),
-            ExampleError::SetErr => cubeos_error::Error::ServiceError(2),
-            ExampleError::I2CError(io) => cubeos_error::Error::from(io),
-            ExampleError::I2CSet => cubeos_error::Error::ServiceError(3),
-            ExampleError::UARTError(io) => cubeos_error::Error::from(io),
+            CubeOSError::ServiceError(NONE_CODE) => Ok(ExampleError::None),
+            CubeOSError::ServiceError(ERR_CODE) => Ok(ExampleError::Err),
+            CubeOSError::ServiceError(SET_ERR_CODE) => Ok(ExampleError::SetErr),
+            CubeOSError::ServiceError(code) if (I2C_CODE_BASE..UART_CODE_BASE).contains(&code) => {
+                I2cError::decode(code)
+                    .map(ExampleError::I2c)
+                    .ok_or(CubeOSError::ServiceError(code))
+            }
+            CubeOSError::ServiceError(code) if code >= UART_CODE_BASE => {
+                UartError::decode(code)
+                    .map(ExampleError::Uart)
+                    .ok_or(CubeOSError::ServiceError(code))
+            }
+            other => Err(other),
         }
     }
 }
-impl From<rust_uart::UartError> for ExampleError {
-    fn from(e: rust_uart::UartError) -> ExampleError {
-        ExampleError::UARTError(e)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trips_semantic_variants() {
+        for e in [ExampleError::None, ExampleError::Err, ExampleError::SetErr] {
+            let wire = CubeOSError::from(e.clone());
+            assert_eq!(ExampleError::try_from(wire).unwrap(), e);
+        }
+    }
+
+    #[test]
+    fn round_trips_i2c_set() {
+        let e = ExampleError::I2c(I2cError::I2CSet);
+        let wire = CubeOSError::from(e.clone());
+        assert_eq!(ExampleError::try_from(wire).unwrap(), e);
+    }
+
+    #[test]
+    fn does_not_round_trip_i2c_error() {
+        // I2CError wraps an io::Error that was never put on the wire (only
+        // its fixed ServiceError code was), so decoding can't reconstruct
+        // it; TryFrom intentionally reports this as Err rather than
+        // fabricating a placeholder error.
+        let e = ExampleError::I2c(I2cError::I2CError(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "boom",
+        )));
+        let wire = CubeOSError::from(e);
+        assert!(ExampleError::try_from(wire).is_err());
+    }
+
+    #[test]
+    fn does_not_round_trip_uart_error() {
+        // UartError::UARTError is the UART bus's only variant, wrapping a
+        // payload (rust_uart::UartError) that never reaches the wire, only
+        // its fixed code (UART_CODE_BASE) does; decoding that code should
+        // fall back to Err the same way I2CError does.
+        let wire = CubeOSError::ServiceError(UART_CODE_BASE);
+        assert!(ExampleError::try_from(wire).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_graphql_input() {
+        let bad = ExampleInputGql {
+            in_no: 0,
+            in_no1: -1,
+            in_no2: 0,
+            in_str: "x".into(),
+            in_bool: true,
+        };
+        match ExampleInput::try_from(bad) {
+            Err(msg) => assert!(msg.contains("in_no1")),
+            Ok(_) => panic!("expected out-of-range in_no1 to be rejected"),
+        }
     }
 }
 
@@ -95,16 +297,62 @@ pub enum ExampleEnum {
     All,
 }
 
+// Defines the bincode/serde wire struct `$name` together with a GraphQL
+// input object mirror `$gql_name`, plus the conversions to move between
+// them, so the two can never drift out of sync the way a hand-maintained
+// mirror in example-service/graphql.rs could. Every field lists its mirror
+// type after `=`; GraphQL's `Int` is a signed 32-bit type, so wire types
+// juniper has no scalar impl for (e.g. `u16`, `u32`) mirror as `i32`.
+//
+// wire -> GraphQL converts with an `as` cast (a `u32` wire value larger than
+// `i32::MAX` wraps to a negative `Int`, since it exceeds GraphQL `Int`'s range).
+// GraphQL -> wire narrows, so it goes through `TryFrom` instead: a client
+// sending an out-of-range or negative `Int` gets a rejected conversion
+// rather than having it silently wrap into the wire type.
+macro_rules! graphql_input {
+    (
+        pub struct $name:ident as $gql_name:ident {
+            $(pub $field:ident : $wire_ty:ty = $gql_ty:ty),* $(,)?
+        }
+    ) => {
+        #[derive(Serialize, Deserialize, Clone)]
+        pub struct $name {
+            $(pub $field: $wire_ty,)*
+        }
+
+        #[derive(GraphQLInputObject)]
+        pub struct $gql_name {
+            $(pub $field: $gql_ty,)*
+        }
+
+        impl From<$name> for $gql_name {
+            fn from(w: $name) -> $gql_name {
+                $gql_name { $($field: w.$field as $gql_ty,)* }
+            }
+        }
+
+        impl std::convert::TryFrom<$gql_name> for $name {
+            type Error = String;
+
+            fn try_from(g: $gql_name) -> Result<$name, String> {
+                Ok($name {
+                    $($field: <$wire_ty as std::convert::TryFrom<$gql_ty>>::try_from(g.$field)
+                        .map_err(|_| format!("{}: value out of range", stringify!($field)))?,)*
+                })
+            }
+        }
+    };
+}
+
 // Example of an Input/Output Struct
-// It is necessary to also define a GraphQL equivalent for input structs
-// (see example-service/graphql.rs)
-#[derive(Serialize,Deserialize)]
-pub struct ExampleInput {
-    pub in_no: u16,
-    pub in_no1: u32,
-    pub in_no2: u16,
-    pub in_str: String,
-    pub in_bool: bool,
+graphql_input! {
+    pub struct ExampleInput as ExampleInputGql {
+        pub in_no: u16 = i32,
+        pub in_no1: u32 = i32,
+        pub in_no2: u16 = i32,
+        pub in_str: String = String,
+        pub in_bool: bool = bool,
+    }
 }
 
 #[derive(Serialize,Deserialize,Debug)]